@@ -1,11 +1,13 @@
-use lib;
-use anyhow;
-use tokio;
-
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     // I could maybe use async to make a spinny for loading
     println!("Finding Database...");
-    let db = lib::db::create_or_get_handle().await?;
+    let db_url = lib::config::resolve_db_url()?;
+    let _db = lib::db::create_or_get_handle(
+        &db_url,
+        lib::db::DEFAULT_MAX_CONNECTIONS,
+        lib::db::DEFAULT_BUSY_TIMEOUT,
+    )
+    .await?;
     Ok(())
 }
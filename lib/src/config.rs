@@ -0,0 +1,60 @@
+//! Resolves the sqlite database URL used by [`crate::db::create_or_get_handle`].
+//!
+//! The old `DB_URL = "sqlite://~/.plannrs.db"` constant never actually
+//! worked - sqlite takes the path literally and never expands `~`. This
+//! module resolves a real, absolute path instead: an explicit override from
+//! the environment if set, otherwise the platform's XDG (or equivalent)
+//! data directory.
+
+use std::path::PathBuf;
+
+use directories::ProjectDirs;
+
+/// Environment variable checked first; lets a user or script point `plannrs`
+/// at a specific database file without touching the XDG data dir.
+const DB_URL_ENV: &str = "PLANNRS_DB_URL";
+
+/// Fallback environment variable, checked after [`DB_URL_ENV`] so the more
+/// generic `DATABASE_URL` convention (e.g. sqlx-cli, other tools in the same
+/// shell) also works.
+const DATABASE_URL_ENV: &str = "DATABASE_URL";
+
+/// Application name passed to [`ProjectDirs`] to locate the XDG (or platform
+/// equivalent) data directory. No qualifier/organisation - `plannrs` is a
+/// one-person project, not published under anyone's namespace.
+const APPLICATION: &str = "plannrs";
+
+/// Resolves the `sqlite://` URL [`crate::db::create_or_get_handle`] should
+/// connect to.
+///
+/// Loads a local `.env` file via `dotenvy` if one is present (silently
+/// ignored if not - this is a convenience for development, not a
+/// requirement), then checks [`DB_URL_ENV`] and [`DATABASE_URL_ENV`] in that
+/// order. If neither is set, resolves `$XDG_DATA_HOME/plannrs/plannrs.db`
+/// (or the platform equivalent), creating the parent directory if it
+/// doesn't exist yet, so the app works on first run without manual setup.
+pub fn resolve_db_url() -> anyhow::Result<String> {
+    dotenvy::dotenv().ok();
+
+    if let Ok(url) = std::env::var(DB_URL_ENV) {
+        return Ok(url);
+    }
+    if let Ok(url) = std::env::var(DATABASE_URL_ENV) {
+        return Ok(url);
+    }
+
+    let path = default_db_path()?;
+    Ok(format!("sqlite://{}", path.display()))
+}
+
+/// Resolves `$XDG_DATA_HOME/plannrs/plannrs.db` (or the platform equivalent
+/// via [`ProjectDirs`]), creating the parent directory if it doesn't exist.
+fn default_db_path() -> anyhow::Result<PathBuf> {
+    let dirs = ProjectDirs::from("", "", APPLICATION)
+        .ok_or_else(|| anyhow::anyhow!("could not determine a home directory for this user"))?;
+
+    let data_dir = dirs.data_dir();
+    std::fs::create_dir_all(data_dir)?;
+
+    Ok(data_dir.join("plannrs.db"))
+}
@@ -0,0 +1,259 @@
+//! Background notification daemon: watches upcoming `Plan`s and fires a
+//! desktop notification ahead of time for any plan with `notify` set and
+//! `done` unset, honouring `Plan::advance` as the lead time. It also warns
+//! when a plan's stored timezone no longer matches the system's current
+//! one, see [`check_tz_drift`].
+
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use chrono::{DateTime, Local, TimeDelta};
+use notify_rust::Notification;
+use sqlx::{Pool, Sqlite};
+use tokio::signal::unix::{signal, SignalKind};
+use tokio_util::sync::CancellationToken;
+
+use crate::db::{self, Plan};
+
+/// Current system IANA zone id, or `None` if it can't be determined -
+/// treated as "assume no drift" rather than failing the whole tick over a
+/// single unreadable `/etc/localtime`.
+fn current_tz_name() -> Option<String> {
+    iana_time_zone::get_timezone().ok()
+}
+
+/// How often the daemon polls for plans that are due a notification.
+pub const DEFAULT_TICK: Duration = Duration::from_secs(30);
+
+/// Runs the notification daemon until SIGINT/SIGTERM arrives.
+///
+/// Each tick, it checks every plan for whether it's now within its
+/// `advance` window of `start`, and fires the due notification - once per
+/// plan per window, see [`check_due_plans`] - and separately checks whether
+/// any plan's stored timezone has drifted from the system's current one,
+/// see [`check_tz_drift`].
+///
+/// The loop selects between the tick timer and the shutdown signal so that,
+/// once Ctrl+C/SIGTERM arrives, it stops taking new ticks, lets whichever
+/// check is in flight finish, and closes `db` cleanly - avoiding a
+/// half-written SQLite file if the process were killed mid-write instead.
+pub async fn run(db: Pool<Sqlite>, tick: Duration) -> anyhow::Result<()> {
+    let shutdown = CancellationToken::new();
+
+    // Ctrl+C/SIGINT or SIGTERM cancels the token; the select! below only
+    // stops taking new ticks once that happens, rather than aborting
+    // whatever check is already in flight.
+    let ctrl_c_shutdown = shutdown.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            ctrl_c_shutdown.cancel();
+        }
+    });
+
+    let sigterm_shutdown = shutdown.clone();
+    let mut sigterm = signal(SignalKind::terminate())?;
+    tokio::spawn(async move {
+        if sigterm.recv().await.is_some() {
+            sigterm_shutdown.cancel();
+        }
+    });
+
+    let mut interval = tokio::time::interval(tick);
+    // IDs of plans a notification has already fired for in the current
+    // advance window, so a 30s tick doesn't re-notify the same plan for the
+    // whole window - see `check_due_plans`.
+    let mut notified = HashSet::new();
+    // IDs of plans already warned about timezone drift, paired with the
+    // `tz_name` they were warned for - see `check_tz_drift`.
+    let mut tz_warned = HashMap::new();
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {
+                check_due_plans(&db, &mut notified).await?;
+                check_tz_drift(&db, &mut tz_warned).await?;
+            }
+            _ = shutdown.cancelled() => {
+                println!("Shutdown signal received, finishing up...");
+                break;
+            }
+        }
+    }
+
+    db.close().await;
+    println!("Notification daemon stopped.");
+
+    Ok(())
+}
+
+/// Checks every plan for a due notification, firing one for each plan that
+/// just entered its `advance` window and isn't already in `notified`.
+///
+/// `notified` persists across ticks (see [`run`]) so a plan only fires once
+/// per window rather than on every tick until `start` arrives. Once a plan
+/// falls out of its window (fired and past `start`, or edited to no longer
+/// be due), its id is dropped from `notified` so a later reschedule can
+/// fire again.
+async fn check_due_plans(db: &Pool<Sqlite>, notified: &mut HashSet<u8>) -> anyhow::Result<()> {
+    let now = Local::now();
+    let plans = db::list_plans(db).await?;
+    let mut still_in_window = HashSet::new();
+
+    for plan in plans {
+        if plan.done || !plan.notify {
+            continue;
+        }
+
+        if is_due(&plan, now) {
+            still_in_window.insert(plan.id);
+            if notified.insert(plan.id) {
+                notify_plan_due(&plan)?;
+            }
+        }
+    }
+
+    notified.retain(|id| still_in_window.contains(id));
+
+    Ok(())
+}
+
+/// Whether `plan` is currently within its notification window: from
+/// `start - advance` up to and including `start` itself.
+///
+/// The upper bound is inclusive because a `None`/zero `advance` plan (a
+/// perfectly normal "just notify me when it starts" config) has
+/// `fire_at == start` - an exclusive `now < start` would make the window
+/// empty and such a plan would never fire.
+fn is_due(plan: &Plan, now: DateTime<Local>) -> bool {
+    let advance = plan.advance.unwrap_or_else(TimeDelta::zero);
+    let fire_at = plan.start - advance;
+
+    now >= fire_at && now <= plan.start
+}
+
+/// Checks every notify-enabled plan's stored [`Plan::tz_name`] against the
+/// system's *current* IANA zone id, firing a "recheck your plan times"
+/// notification for any that no longer match.
+///
+/// Comparing zone ids rather than UTC offsets is deliberate: `start`'s
+/// offset and `now`'s offset can differ across an ordinary DST transition
+/// even though the system's timezone never changed, which would make an
+/// offset comparison fire constantly. Comparing the zone id instead only
+/// fires when the system's configured timezone has actually changed (e.g.
+/// the user travelled) since the plan was last written.
+///
+/// `tz_warned` maps a plan's id to the `tz_name` it was already warned
+/// about, persisting across ticks so the warning fires once per drift
+/// rather than on every tick - and fires again if the system moves to yet
+/// another zone. It's cleared for a plan once its stored `tz_name` matches
+/// the current zone again, e.g. after the user edits the plan or travels
+/// back.
+async fn check_tz_drift(db: &Pool<Sqlite>, tz_warned: &mut HashMap<u8, String>) -> anyhow::Result<()> {
+    let Some(current_tz) = current_tz_name() else {
+        return Ok(());
+    };
+
+    let plans = db::list_plans(db).await?;
+
+    for plan in plans {
+        if plan.done || !plan.notify {
+            continue;
+        }
+
+        if plan.tz_name == current_tz {
+            tz_warned.remove(&plan.id);
+            continue;
+        }
+
+        if tz_warned.get(&plan.id) != Some(&current_tz) {
+            notify_tz_drift(&plan)?;
+            tz_warned.insert(plan.id, current_tz.clone());
+        }
+    }
+
+    Ok(())
+}
+
+/// Fires the "it's nearly time for this plan" notification.
+fn notify_plan_due(plan: &Plan) -> anyhow::Result<()> {
+    Notification::new()
+        .summary(&plan.name)
+        .body(&plan.description)
+        .show()?;
+
+    Ok(())
+}
+
+/// Fires the "recheck your plan times" notification for a plan whose
+/// stored timezone no longer matches the system's current one.
+fn notify_tz_drift(plan: &Plan) -> anyhow::Result<()> {
+    Notification::new()
+        .summary(&format!("Timezone changed since \"{}\" was scheduled", plan.name))
+        .body("Your system timezone has changed - recheck this plan's start/until times.")
+        .show()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    /// Builds a `Plan` starting at `start_hm` on a fixed day, with the
+    /// given `advance`, for exercising [`is_due`] in isolation.
+    fn plan_starting_at(start_hm: (u32, u32), advance: Option<TimeDelta>) -> Plan {
+        Plan {
+            id: 0,
+            name: "Study".to_string(),
+            description: String::new(),
+            start: Local.with_ymd_and_hms(2026, 7, 28, start_hm.0, start_hm.1, 0).unwrap(),
+            until: Local.with_ymd_and_hms(2026, 7, 28, start_hm.0 + 1, start_hm.1, 0).unwrap(),
+            advance,
+            done: false,
+            tags: Vec::new(),
+            notify: true,
+            porsmo: false,
+            tz_name: String::new(),
+        }
+    }
+
+    #[test]
+    fn fires_exactly_at_start_with_no_advance() {
+        let plan = plan_starting_at((9, 0), None);
+        let now = Local.with_ymd_and_hms(2026, 7, 28, 9, 0, 0).unwrap();
+
+        assert!(is_due(&plan, now), "a None-advance plan must fire at its start time");
+    }
+
+    #[test]
+    fn does_not_fire_before_start_with_no_advance() {
+        let plan = plan_starting_at((9, 0), None);
+        let now = Local.with_ymd_and_hms(2026, 7, 28, 8, 59, 0).unwrap();
+
+        assert!(!is_due(&plan, now));
+    }
+
+    #[test]
+    fn does_not_fire_after_start_with_no_advance() {
+        let plan = plan_starting_at((9, 0), None);
+        let now = Local.with_ymd_and_hms(2026, 7, 28, 9, 0, 1).unwrap();
+
+        assert!(!is_due(&plan, now));
+    }
+
+    #[test]
+    fn fires_throughout_the_advance_window() {
+        let plan = plan_starting_at((9, 0), Some(TimeDelta::minutes(10)));
+
+        let window_start = Local.with_ymd_and_hms(2026, 7, 28, 8, 50, 0).unwrap();
+        let mid_window = Local.with_ymd_and_hms(2026, 7, 28, 8, 55, 0).unwrap();
+        let window_end = Local.with_ymd_and_hms(2026, 7, 28, 9, 0, 0).unwrap();
+        let before_window = Local.with_ymd_and_hms(2026, 7, 28, 8, 49, 59).unwrap();
+
+        assert!(is_due(&plan, window_start));
+        assert!(is_due(&plan, mid_window));
+        assert!(is_due(&plan, window_end));
+        assert!(!is_due(&plan, before_window));
+    }
+}
@@ -1,30 +1,54 @@
 //! The types and structs to represent the tables in the database, as
 //! well as basic database interaction functions
 
-use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
 use ratatui::style::Color;
-use chrono::{DateTime, TimeDelta, Local};
-use sqlx::{migrate::MigrateDatabase, prelude::*, Sqlite, SqlitePool, Pool};
-use anyhow::{self, Ok};
+use chrono::{DateTime, TimeDelta, Local, TimeZone};
+use sqlx::{
+    migrate::MigrateDatabase,
+    prelude::*,
+    sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteRow},
+    Sqlite, Pool,
+};
 
-/// The DB URL. Need to experiment to see what works best.
-pub const DB_URL: &str = "sqlite://~/.plannrs.db";
+/// Default number of pooled connections handed to [`create_or_get_handle`]
+/// when the caller doesn't need to tune it. The TUI and the notification
+/// daemon each get their own pool, so this stays modest.
+pub const DEFAULT_MAX_CONNECTIONS: u32 = 5;
+
+/// Default `busy_timeout`: how long a connection will wait for a lock held by
+/// another writer before giving up with `SQLITE_BUSY`.
+pub const DEFAULT_BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The sentinel stored in the `Border`/`Fill` columns to mean "no colour set",
+/// i.e. the field should decode to `None`. `u8::MAX` is reserved for this
+/// since `encode_color` never produces it for a real `Color`.
+const NO_COLOR_SENTINEL: u8 = u8::MAX;
+
+/// The sentinel stored in the `Advance` column to mean `None`, i.e. the plan
+/// has no notification lead time configured. Negative durations are
+/// meaningless here; [`encode_advance`] rejects an advance of exactly `-1`
+/// seconds so it can't be stored and read back as `None`.
+const NO_ADVANCE_SENTINEL: i64 = -1;
 
 /// Tags are used to group data by subject - for example Maths or Chores.
 /// These can be represented in the TUI using different colours. The colours
 /// used are `ratatui::style::Color`, which use the ANSI colour table.
 /// The theming of the colours can be changed by using different terminal
-/// themes. 
+/// themes.
 /// A Tag in the database could be constructed through:
-/// ```
-/// let maths_tag: Tag { 
-///     id: 0, 
-///     name: String::from("Maths"), 
+/// ```ignore
+/// // `Tag`'s fields are `pub(crate)`, so this can't actually run as an
+/// // external doctest - illustrative only.
+/// let maths_tag = Tag {
+///     id: 0,
+///     name: String::from("Maths"),
 ///     border: None,
 ///     fill: Some(Color::White),
-///     color: Color::Black
+///     color: Color::Black,
 /// };
-/// 
+///
 /// assert!(maths_tag.color == Color::Black);
 /// ```
 pub struct Tag {
@@ -40,21 +64,41 @@ pub struct Tag {
     pub(crate) color: Color,
 }
 
+impl FromRow<'_, SqliteRow> for Tag {
+    fn from_row(row: &SqliteRow) -> sqlx::Result<Self> {
+        let border: i64 = row.try_get("Border")?;
+        let fill: i64 = row.try_get("Fill")?;
+        let color: i64 = row.try_get("Color")?;
+
+        Ok(Tag {
+            id: rowid_to_id(row.try_get("ID")?).map_err(|e| sqlx::Error::Decode(e.into()))?,
+            name: row.try_get("TagName")?,
+            border: decode_optional_color(border as u8),
+            fill: decode_optional_color(fill as u8),
+            color: decode_color(color as u8),
+        })
+    }
+}
+
 /// The plan table for the database. Has all of the relevant information needed.
-/// In the actual database, `tag` will be the `Tag.id`. It is just simpler for
-/// the sake of abstraction here to use the whole struct as we will be fetching
-/// it whenever we want to fetch the tag anyway.
+/// In the actual database, `tags` is resolved through the `PlanTag` junction
+/// table rather than stored on `Plan` directly. It is just simpler for the
+/// sake of abstraction here to use the whole `Tag` structs as we will be
+/// fetching them whenever we want to fetch the plan anyway.
 pub struct Plan {
-    /// The Id for each entry in the plan table. It would be possible to 
-    /// have the primary key be the start datetime as I do not plan to allow 
+    /// The Id for each entry in the plan table. It would be possible to
+    /// have the primary key be the start datetime as I do not plan to allow
     /// users to have two study sessions at once (I do not think that it would
     /// be feasible to rewrite all of the other systems just to accomodate it).
     /// However, I think that I may as well just use IDs for it as it will always
     /// be simpler.
     pub(crate) id: u8,
+    /// The short name for the plan, shown in the timeline before the
+    /// description (`PlanName` in the schema).
+    pub(crate) name: String,
     /// This is the description of the plan or study session. A user could
-    /// write a short or a longer description (we would have to have a button to 
-    /// toggle an expanded popup for the text, and possibly limit the number 
+    /// write a short or a longer description (we would have to have a button to
+    /// toggle an expanded popup for the text, and possibly limit the number
     /// of characters based off of that larger view. Most people will not see
     /// this part anyway, but it is worth thinking about.)
     pub(crate) description: String,
@@ -66,9 +110,9 @@ pub struct Plan {
     /// Similar to the start time, this should only have issues if the user
     /// changes timezone. This will probably be an unlikely circumstance.
     /// However, I think that when they are in the new timezone, the displayed
-    /// time will update to match so the user can change the time in the DB 
+    /// time will update to match so the user can change the time in the DB
     /// manaully. There could also be a warning in the daemon that triggers
-    /// a notification to do so if the timezone has changed. 
+    /// a notification to do so if the timezone has changed.
     pub(crate) until: DateTime<Local>,
     /// This is the amount of time before the start that the notification should
     /// appear. We will only use the seconds for this most likely, in increments
@@ -77,56 +121,539 @@ pub struct Plan {
     /// This is a flag for if the task has been completed or done. This can
     /// be a user changed checkmark on each task.
     pub(crate) done: bool,
-    /// This is the option for the plan to be associated with a tag. If it 
-    /// is not associated with a tag, it will have a default grey/black/white
-    /// colour scheme in the timeline. 
-    pub(crate) tag: Option<Tag>,
+    /// The `Tag`s this plan belongs to, resolved through the `PlanTag`
+    /// junction table so a session can be e.g. both "Maths" and "Exam Prep".
+    /// If empty, it will have a default grey/black/white colour scheme in
+    /// the timeline. Populated separately from `FromRow`, see
+    /// [`load_plan_tags`].
+    pub(crate) tags: Vec<Tag>,
     /// This is a flag for if the notification will sound on the desktop
-    /// or not. 
+    /// or not.
     pub(crate) notify: bool,
     /// This is a flag for porsmo integration. Planned, but not likely anytime
     /// soon.
     pub(crate) porsmo: bool,
+    /// The IANA zone id (e.g. `"Europe/London"`) the system was in when
+    /// this plan was last written, captured via `iana_time_zone`. Unlike
+    /// comparing `start.offset()` to `Local::now().offset()`, this isn't
+    /// thrown off by an ordinary DST transition between now and `start` -
+    /// the daemon only warns when this differs from the *current* system
+    /// zone id, which means the user actually changed timezones (e.g.
+    /// travelled) since the plan was stored. See
+    /// [`crate::daemon::check_tz_drift`].
+    pub(crate) tz_name: String,
+}
+
+impl FromRow<'_, SqliteRow> for Plan {
+    fn from_row(row: &SqliteRow) -> sqlx::Result<Self> {
+        let start: i64 = row.try_get("StartTime")?;
+        let until: i64 = row.try_get("Until")?;
+        let advance: i64 = row.try_get("Advance")?;
+
+        Ok(Plan {
+            id: rowid_to_id(row.try_get("ID")?).map_err(|e| sqlx::Error::Decode(e.into()))?,
+            name: row.try_get("PlanName")?,
+            description: row.try_get("Descr")?,
+            start: decode_datetime(start).map_err(|e| sqlx::Error::Decode(e.into()))?,
+            until: decode_datetime(until).map_err(|e| sqlx::Error::Decode(e.into()))?,
+            advance: decode_advance(advance).map_err(|e| sqlx::Error::Decode(e.into()))?,
+            done: row.try_get("Done")?,
+            // `PlanTag` is a separate table, keyed by this row's `ID`, so it
+            // can't be resolved from a single `Plan` row - see
+            // `load_plan_tags` and its callers.
+            tags: Vec::new(),
+            notify: row.try_get("Notify")?,
+            porsmo: row.try_get("Porsmo")?,
+            tz_name: row.try_get("TzName")?,
+        })
+    }
+}
+
+/// Maps a `Color` onto the `u8` stored in the `Border`/`Fill`/`Color`
+/// `TINYINT` columns. The 16 named ANSI variants get their conventional
+/// ANSI index (0-15), `Color::Indexed` is passed through as-is, and anything
+/// that can't be represented in the ANSI table (truecolor, `Reset`) is
+/// rejected with a clear error rather than silently truncated.
+fn encode_color(color: Color) -> anyhow::Result<u8> {
+    use Color::*;
+    let code = match color {
+        Black => 0,
+        Red => 1,
+        Green => 2,
+        Yellow => 3,
+        Blue => 4,
+        Magenta => 5,
+        Cyan => 6,
+        Gray => 7,
+        DarkGray => 8,
+        LightRed => 9,
+        LightGreen => 10,
+        LightYellow => 11,
+        LightBlue => 12,
+        LightMagenta => 13,
+        LightCyan => 14,
+        White => 15,
+        Indexed(i) => i,
+        Rgb(r, g, b) => anyhow::bail!(
+            "truecolor Color::Rgb({r}, {g}, {b}) cannot be stored in the ANSI TINYINT column; \
+             use a named or Color::Indexed colour instead"
+        ),
+        Reset => anyhow::bail!("Color::Reset cannot be stored in the database"),
+    };
+
+    if code == NO_COLOR_SENTINEL {
+        anyhow::bail!("Color::Indexed({NO_COLOR_SENTINEL}) is reserved to mean \"no colour\" and can't be stored directly");
+    }
+
+    Ok(code)
+}
+
+/// The inverse of [`encode_color`]: ANSI indices 0-15 decode back to their
+/// named variant, everything else becomes `Color::Indexed`.
+fn decode_color(value: u8) -> Color {
+    use Color::*;
+    match value {
+        0 => Black,
+        1 => Red,
+        2 => Green,
+        3 => Yellow,
+        4 => Blue,
+        5 => Magenta,
+        6 => Cyan,
+        7 => Gray,
+        8 => DarkGray,
+        9 => LightRed,
+        10 => LightGreen,
+        11 => LightYellow,
+        12 => LightBlue,
+        13 => LightMagenta,
+        14 => LightCyan,
+        15 => White,
+        other => Indexed(other),
+    }
+}
+
+/// Encodes an `Option<Color>`, using [`NO_COLOR_SENTINEL`] for `None` so the
+/// `Border`/`Fill` columns can stay `NOT NULL TINYINT`.
+fn encode_optional_color(color: Option<Color>) -> anyhow::Result<u8> {
+    match color {
+        Some(c) => encode_color(c),
+        None => Ok(NO_COLOR_SENTINEL),
+    }
+}
+
+/// The inverse of [`encode_optional_color`].
+fn decode_optional_color(value: u8) -> Option<Color> {
+    if value == NO_COLOR_SENTINEL {
+        None
+    } else {
+        Some(decode_color(value))
+    }
+}
+
+/// Encodes a `DateTime<Local>` as a UNIX epoch second count for the
+/// `StartTime`/`Until` `INT` columns.
+fn encode_datetime(dt: DateTime<Local>) -> i64 {
+    dt.timestamp()
+}
+
+/// The inverse of [`encode_datetime`]. An epoch second is a single UTC
+/// instant, so it always maps onto exactly one local time; this only fails
+/// if `secs` is outside the range chrono can represent.
+fn decode_datetime(secs: i64) -> anyhow::Result<DateTime<Local>> {
+    Local
+        .timestamp_opt(secs, 0)
+        .single()
+        .ok_or_else(|| anyhow::anyhow!("timestamp {secs} does not map onto a unique local time"))
+}
+
+/// Encodes `Option<TimeDelta>` as seconds for the `Advance` `INT` column,
+/// using [`NO_ADVANCE_SENTINEL`] for `None`.
+fn encode_advance(advance: Option<TimeDelta>) -> anyhow::Result<i64> {
+    match advance {
+        Some(delta) => {
+            let secs = delta.num_seconds();
+            if secs == NO_ADVANCE_SENTINEL {
+                anyhow::bail!("an advance of exactly {NO_ADVANCE_SENTINEL} seconds can't be stored, it is reserved to mean \"no advance\"");
+            }
+            Ok(secs)
+        }
+        None => Ok(NO_ADVANCE_SENTINEL),
+    }
+}
+
+/// The inverse of [`encode_advance`].
+fn decode_advance(secs: i64) -> anyhow::Result<Option<TimeDelta>> {
+    if secs == NO_ADVANCE_SENTINEL {
+        return Ok(None);
+    }
+    TimeDelta::try_seconds(secs)
+        .map(Some)
+        .ok_or_else(|| anyhow::anyhow!("advance of {secs} seconds is out of range"))
+}
+
+/// The columns fetched for a `Plan` row on its own - `Plan.tags` is resolved
+/// separately through [`load_plan_tags`], since a single `Plan` row can't
+/// carry a variable number of joined `Tag`s.
+const PLAN_SELECT: &str = "SELECT * FROM Plan";
+
+/// Loads every `Tag` linked to `plan_id` through the `PlanTag` junction
+/// table, aggregating all of that plan's rows in the junction table.
+pub async fn load_plan_tags(db: &Pool<Sqlite>, plan_id: u8) -> anyhow::Result<Vec<Tag>> {
+    let tags = sqlx::query_as::<_, Tag>(
+        "SELECT Tag.* FROM Tag
+         JOIN PlanTag ON PlanTag.TagID = Tag.ID
+         WHERE PlanTag.PlanID = ?
+         ORDER BY Tag.ID",
+    )
+    .bind(plan_id)
+    .fetch_all(db)
+    .await?;
+
+    Ok(tags)
 }
 
-/// This function gets a handle to a database described by DB_URL.
-/// 
+/// Links `tag_id` to `plan_id` in the `PlanTag` junction table. A no-op if
+/// the link already exists.
+pub async fn link_tag(db: &Pool<Sqlite>, plan_id: u8, tag_id: u8) -> anyhow::Result<()> {
+    sqlx::query("INSERT OR IGNORE INTO PlanTag (PlanID, TagID) VALUES (?, ?)")
+        .bind(plan_id)
+        .bind(tag_id)
+        .execute(db)
+        .await?;
+
+    Ok(())
+}
+
+/// Removes the link between `tag_id` and `plan_id`, if it exists.
+pub async fn unlink_tag(db: &Pool<Sqlite>, plan_id: u8, tag_id: u8) -> anyhow::Result<()> {
+    sqlx::query("DELETE FROM PlanTag WHERE PlanID = ? AND TagID = ?")
+        .bind(plan_id)
+        .bind(tag_id)
+        .execute(db)
+        .await?;
+
+    Ok(())
+}
+
+/// Replaces every `PlanTag` link for `plan_id` with `tags`. Used by
+/// [`insert_plan`]/[`update_plan`] to keep the junction table in sync with
+/// `Plan.tags` without the caller having to diff the old and new sets.
+async fn sync_plan_tags(db: &Pool<Sqlite>, plan_id: u8, tags: &[Tag]) -> anyhow::Result<()> {
+    sqlx::query("DELETE FROM PlanTag WHERE PlanID = ?")
+        .bind(plan_id)
+        .execute(db)
+        .await?;
+
+    for tag in tags {
+        link_tag(db, plan_id, tag.id).await?;
+    }
+
+    Ok(())
+}
+
+/// Converts a `last_insert_rowid()` result into the `u8` id type used
+/// throughout this module, erroring instead of silently truncating if
+/// sqlite ever assigns a rowid outside `u8`'s range.
+fn rowid_to_id(rowid: i64) -> anyhow::Result<u8> {
+    u8::try_from(rowid).map_err(|_| anyhow::anyhow!("row id {rowid} does not fit in a u8"))
+}
+
+/// Inserts a new `Tag`, returning the row ID sqlite assigned it. Runs in a
+/// transaction so that a rowid overflowing [`rowid_to_id`]'s `u8` range
+/// rolls the insert back instead of leaving an uncommitted-from-the-
+/// caller's-perspective row in the table.
+pub async fn insert_tag(db: &Pool<Sqlite>, tag: &Tag) -> anyhow::Result<u8> {
+    let border = encode_optional_color(tag.border)?;
+    let fill = encode_optional_color(tag.fill)?;
+    let color = encode_color(tag.color)?;
+
+    let mut tx = db.begin().await?;
+
+    let result = sqlx::query(
+        "INSERT INTO Tag (TagName, Border, Fill, Color) VALUES (?, ?, ?, ?)",
+    )
+    .bind(&tag.name)
+    .bind(border)
+    .bind(fill)
+    .bind(color)
+    .execute(&mut *tx)
+    .await?;
+
+    let id = rowid_to_id(result.last_insert_rowid())?;
+    tx.commit().await?;
+
+    Ok(id)
+}
+
+/// Fetches a single `Tag` by ID, if it exists.
+pub async fn get_tag(db: &Pool<Sqlite>, id: u8) -> anyhow::Result<Option<Tag>> {
+    let tag = sqlx::query_as::<_, Tag>("SELECT * FROM Tag WHERE ID = ?")
+        .bind(id)
+        .fetch_optional(db)
+        .await?;
+
+    Ok(tag)
+}
+
+/// Fetches every `Tag` in the database.
+pub async fn list_tags(db: &Pool<Sqlite>) -> anyhow::Result<Vec<Tag>> {
+    let tags = sqlx::query_as::<_, Tag>("SELECT * FROM Tag")
+        .fetch_all(db)
+        .await?;
+
+    Ok(tags)
+}
+
+/// Overwrites an existing `Tag`'s fields, matched by `tag.id`.
+pub async fn update_tag(db: &Pool<Sqlite>, tag: &Tag) -> anyhow::Result<()> {
+    let border = encode_optional_color(tag.border)?;
+    let fill = encode_optional_color(tag.fill)?;
+    let color = encode_color(tag.color)?;
+
+    sqlx::query("UPDATE Tag SET TagName = ?, Border = ?, Fill = ?, Color = ? WHERE ID = ?")
+        .bind(&tag.name)
+        .bind(border)
+        .bind(fill)
+        .bind(color)
+        .bind(tag.id)
+        .execute(db)
+        .await?;
+
+    Ok(())
+}
+
+/// Deletes a `Tag` by ID, along with any `PlanTag` links to it.
+pub async fn delete_tag(db: &Pool<Sqlite>, id: u8) -> anyhow::Result<()> {
+    sqlx::query("DELETE FROM PlanTag WHERE TagID = ?")
+        .bind(id)
+        .execute(db)
+        .await?;
+
+    sqlx::query("DELETE FROM Tag WHERE ID = ?")
+        .bind(id)
+        .execute(db)
+        .await?;
+
+    Ok(())
+}
+
+/// The system's current IANA zone id (e.g. `"Europe/London"`), stored on a
+/// `Plan` at write time so the daemon can later tell a real timezone change
+/// apart from an ordinary DST transition - see [`Plan::tz_name`].
+fn current_tz_name() -> anyhow::Result<String> {
+    iana_time_zone::get_timezone()
+        .map_err(|e| anyhow::anyhow!("could not determine the system's IANA timezone: {e}"))
+}
+
+/// Inserts a new `Plan` and links each of `plan.tags` to it through the
+/// `PlanTag` junction table, returning the row ID sqlite assigned the plan.
+/// The insert and the `rowid_to_id` range check run in a transaction, so a
+/// rowid overflowing `u8` rolls the insert back instead of leaving the
+/// oversized row committed despite the `Err` returned to the caller.
+pub async fn insert_plan(db: &Pool<Sqlite>, plan: &Plan) -> anyhow::Result<u8> {
+    let start = encode_datetime(plan.start);
+    let until = encode_datetime(plan.until);
+    let advance = encode_advance(plan.advance)?;
+    let tz_name = current_tz_name()?;
+
+    let mut tx = db.begin().await?;
+
+    let result = sqlx::query(
+        "INSERT INTO Plan (PlanName, Descr, StartTime, Until, Advance, Done, Notify, Porsmo, TzName)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&plan.name)
+    .bind(&plan.description)
+    .bind(start)
+    .bind(until)
+    .bind(advance)
+    .bind(plan.done)
+    .bind(plan.notify)
+    .bind(plan.porsmo)
+    .bind(tz_name)
+    .execute(&mut *tx)
+    .await?;
+
+    let id = rowid_to_id(result.last_insert_rowid())?;
+    tx.commit().await?;
+
+    sync_plan_tags(db, id, &plan.tags).await?;
+
+    Ok(id)
+}
+
+/// Fetches a single `Plan` by ID, with its `Tag`s resolved, if it exists.
+pub async fn get_plan(db: &Pool<Sqlite>, id: u8) -> anyhow::Result<Option<Plan>> {
+    let query = format!("{PLAN_SELECT} WHERE ID = ?");
+    let plan = sqlx::query_as::<_, Plan>(&query)
+        .bind(id)
+        .fetch_optional(db)
+        .await?;
+
+    let Some(mut plan) = plan else {
+        return Ok(None);
+    };
+    plan.tags = load_plan_tags(db, id).await?;
+
+    Ok(Some(plan))
+}
+
+/// Fetches every `Plan` in the database, with each `Tag` resolved.
+pub async fn list_plans(db: &Pool<Sqlite>) -> anyhow::Result<Vec<Plan>> {
+    let mut plans = sqlx::query_as::<_, Plan>(PLAN_SELECT)
+        .fetch_all(db)
+        .await?;
+
+    for plan in &mut plans {
+        plan.tags = load_plan_tags(db, plan.id).await?;
+    }
+
+    Ok(plans)
+}
+
+/// Overwrites an existing `Plan`'s fields, matched by `plan.id`, re-stamps
+/// `TzName` with the system's current zone (see [`Plan::tz_name`]), and
+/// re-syncs its `PlanTag` links to match `plan.tags`.
+pub async fn update_plan(db: &Pool<Sqlite>, plan: &Plan) -> anyhow::Result<()> {
+    let start = encode_datetime(plan.start);
+    let until = encode_datetime(plan.until);
+    let advance = encode_advance(plan.advance)?;
+    let tz_name = current_tz_name()?;
+
+    sqlx::query(
+        "UPDATE Plan SET PlanName = ?, Descr = ?, StartTime = ?, Until = ?, Advance = ?,
+         Done = ?, Notify = ?, Porsmo = ?, TzName = ? WHERE ID = ?",
+    )
+    .bind(&plan.name)
+    .bind(&plan.description)
+    .bind(start)
+    .bind(until)
+    .bind(advance)
+    .bind(plan.done)
+    .bind(plan.notify)
+    .bind(plan.porsmo)
+    .bind(tz_name)
+    .bind(plan.id)
+    .execute(db)
+    .await?;
+
+    sync_plan_tags(db, plan.id, &plan.tags).await?;
+
+    Ok(())
+}
+
+/// Deletes a `Plan` by ID, along with its `PlanTag` links.
+pub async fn delete_plan(db: &Pool<Sqlite>, id: u8) -> anyhow::Result<()> {
+    sqlx::query("DELETE FROM PlanTag WHERE PlanID = ?")
+        .bind(id)
+        .execute(db)
+        .await?;
+
+    sqlx::query("DELETE FROM Plan WHERE ID = ?")
+        .bind(id)
+        .execute(db)
+        .await?;
+
+    Ok(())
+}
+
+/// This function gets a handle to the database at `db_url`. Callers should
+/// resolve that URL with [`crate::config::resolve_db_url`] rather than
+/// hardcoding one, since sqlite won't expand `~` or create missing parent
+/// directories on its own.
+///
+/// `max_connections` and `busy_timeout` are exposed so that the TUI and the
+/// notification daemon can tune their own pools independently - the daemon
+/// only ever needs a couple of connections, while the TUI may want more for
+/// responsiveness. The pool is opened in WAL journal mode, which lets one
+/// writer coexist with any number of readers, and `busy_timeout` makes a
+/// second writer retry for a while instead of immediately failing with
+/// `"database is locked"` when both the daemon and the TUI touch the file
+/// at once.
+///
 /// # Table information
 /// ```sql
 /// TABLE Tag (
-///     ID INT NOT NULL,
+///     ID INTEGER PRIMARY KEY,
 ///     TagName TEXT NOT NULL,
 ///     Border TINYINT NOT NULL,
 ///     Fill TINYINT NOT NULL,
-///     Color TINYINT NOT NULL,
-///     PRIMARY KEY (ID)
+///     Color TINYINT NOT NULL
 /// );
-/// 
+///
 /// TABLE Plan (
-///     ID INT NOT NULL,
+///     ID INTEGER PRIMARY KEY,
 ///     PlanName TEXT NOT NULL,
 ///     Descr TEXT NOT NULL,
 ///     StartTime INT NOT NULL,
 ///     Until INT NOT NULL,
-///     Advance INT NOT NULL, 
+///     Advance INT NOT NULL,
 ///     Done BOOLEAN NOT NULL,
-///     TagID INT NOT NULL,
 ///     Notify BOOLEAN NOT NULL,
 ///     Porsmo BOOLEAN NOT NULL,
+///     TzName TEXT NOT NULL
+/// );
+///
+/// TABLE PlanTag (
+///     ID INTEGER NOT NULL,
+///     PlanID INT NOT NULL,
+///     TagID INT NOT NULL,
 ///     PRIMARY KEY (ID),
-///     FOREIGN KEY (TagID) REFERENCES Tag(ID)
+///     FOREIGN KEY (PlanID) REFERENCES Plan(ID),
+///     FOREIGN KEY (TagID) REFERENCES Tag(ID),
+///     UNIQUE (PlanID, TagID)
 /// );
 /// ```
 /// # Notes
-/// We use `TINYINT` for the ANSI colour values because we don't need it to be 
+/// We use `TINYINT` for the ANSI colour values because we don't need it to be
 /// any bigger. `StartTime` and `Until` are `DateTime`s, but Sqlite requires
 /// storage as an `INT`. This will represent UNIX Epoch time. `Advance` is in seconds.
-/// 
-pub async fn create_or_get_handle() -> anyhow::Result<Box<Pool<Sqlite>>> {
-    match !Sqlite::database_exists(DB_URL).await? {
+/// `Tag.ID`/`Plan.ID` must be declared `INTEGER PRIMARY KEY` (not `INT`) so
+/// sqlite treats them as rowid aliases - only that exact spelling gets
+/// auto-populated from `last_insert_rowid()`, which [`insert_tag`] and
+/// [`insert_plan`] both rely on.
+/// A `Plan` can have any number of `Tag`s (including none), so the
+/// relationship is modelled through the `PlanTag` junction table rather than
+/// a `TagID` column on `Plan` directly - see [`migrate_single_tag_to_junction`]
+/// for how older single-`TagID` databases are carried forward. `TzName` is
+/// the IANA zone id the system was in when the row was last written - see
+/// [`Plan::tz_name`] and [`migrate_add_tz_name`] for how databases from
+/// before this column existed are carried forward.
+///
+pub async fn create_or_get_handle(
+    db_url: &str,
+    max_connections: u32,
+    busy_timeout: Duration,
+) -> anyhow::Result<Box<Pool<Sqlite>>> {
+    let connect_options = SqliteConnectOptions::from_str(db_url)?
+        .create_if_missing(true)
+        .journal_mode(SqliteJournalMode::Wal)
+        .busy_timeout(busy_timeout);
+
+    match Sqlite::database_exists(db_url).await? {
         true => {
             println!("Database found...");
-            Ok(Box::new(SqlitePool::connect(DB_URL).await?))
+            let db = SqlitePoolOptions::new()
+                .max_connections(max_connections)
+                .connect_with(connect_options)
+                .await?;
+
+            sqlx::query("
+                CREATE TABLE IF NOT EXISTS PlanTag (
+                    ID INTEGER PRIMARY KEY AUTOINCREMENT,
+                    PlanID INT NOT NULL,
+                    TagID INT NOT NULL,
+                    FOREIGN KEY (PlanID) REFERENCES Plan(ID),
+                    FOREIGN KEY (TagID) REFERENCES Tag(ID),
+                    UNIQUE (PlanID, TagID)
+                );
+            ").execute(&db).await?;
+
+            migrate_single_tag_to_junction(&db).await?;
+            migrate_add_tz_name(&db).await?;
+
+            Ok(Box::new(db))
         },
         false => {
             // We will try and keep our transactions as transparent with the
@@ -134,18 +661,21 @@ pub async fn create_or_get_handle() -> anyhow::Result<Box<Pool<Sqlite>>> {
             // can easily be sent on as an issue.
             println!("Database not found, creating new...");
 
-            // Get pool connection
-            let db = SqlitePool::connect(DB_URL).await?;
+            // Get pool connection. `create_if_missing` above means this also
+            // creates the file.
+            let db = SqlitePoolOptions::new()
+                .max_connections(max_connections)
+                .connect_with(connect_options)
+                .await?;
 
             // Attempt to make the Tag Table, print results
             let tag_result = sqlx::query("
                 CREATE TABLE IF NOT EXISTS Tag (
-                    ID INT NOT NULL,
+                    ID INTEGER PRIMARY KEY,
                     TagName TEXT NOT NULL,
                     Border TINYINT NOT NULL,
                     Fill TINYINT NOT NULL,
-                    Color TINYINT NOT NULL,
-                    PRIMARY KEY (ID)
+                    Color TINYINT NOT NULL
                 );
             ").execute(&db).await?;
             println!("Tag Table... Status: {:?}", tag_result);
@@ -153,25 +683,228 @@ pub async fn create_or_get_handle() -> anyhow::Result<Box<Pool<Sqlite>>> {
             // Attempt create plan table, print results.
             let plan_result = sqlx::query("
                 CREATE TABLE IF NOT EXISTS Plan (
-                    ID INT NOT NULL,
+                    ID INTEGER PRIMARY KEY,
                     PlanName TEXT NOT NULL,
                     Descr TEXT NOT NULL,
                     StartTime INT NOT NULL,
                     Until INT NOT NULL,
-                    Advance INT NOT NULL, 
+                    Advance INT NOT NULL,
                     Done BOOLEAN NOT NULL,
-                    TagID INT NOT NULL,
                     Notify BOOLEAN NOT NULL,
                     Porsmo BOOLEAN NOT NULL,
-                    PRIMARY KEY (ID),
-                    FOREIGN KEY (TagID) REFERENCES Tag(ID)
+                    TzName TEXT NOT NULL
                 );
             ").execute(&db).await?;
             println!("Plan table... Status: {:?}", plan_result);
 
+            // Attempt to make the PlanTag junction table, print results. A
+            // fresh database never has legacy `Plan.TagID` rows to migrate,
+            // so unlike the `true` branch above there is nothing to port in.
+            let plan_tag_result = sqlx::query("
+                CREATE TABLE IF NOT EXISTS PlanTag (
+                    ID INTEGER PRIMARY KEY AUTOINCREMENT,
+                    PlanID INT NOT NULL,
+                    TagID INT NOT NULL,
+                    FOREIGN KEY (PlanID) REFERENCES Plan(ID),
+                    FOREIGN KEY (TagID) REFERENCES Tag(ID),
+                    UNIQUE (PlanID, TagID)
+                );
+            ").execute(&db).await?;
+            println!("PlanTag table... Status: {:?}", plan_tag_result);
+
             println!("All seems OK... returning database handle...");
             Ok(Box::new(db))
         },
     }
 }
 
+/// Migrates databases created before multi-tag support: if `Plan` still has
+/// its old single `TagID` column, copies every non-null value into the
+/// `PlanTag` junction table and then rebuilds `Plan` without the column.
+/// Idempotent and a no-op on databases that never had the column (including
+/// brand new ones).
+async fn migrate_single_tag_to_junction(db: &Pool<Sqlite>) -> anyhow::Result<()> {
+    let columns: Vec<(String,)> = sqlx::query_as("SELECT name FROM pragma_table_info('Plan')")
+        .fetch_all(db)
+        .await?;
+
+    if !columns.iter().any(|(name,)| name == "TagID") {
+        return Ok(());
+    }
+
+    println!("Migrating Plan.TagID rows into the PlanTag junction table...");
+
+    sqlx::query(
+        "INSERT OR IGNORE INTO PlanTag (PlanID, TagID)
+         SELECT ID, TagID FROM Plan WHERE TagID IS NOT NULL",
+    )
+    .execute(db)
+    .await?;
+
+    // The legacy schema declares `FOREIGN KEY (TagID) REFERENCES Tag(ID)` on
+    // `Plan`, and sqlite refuses `ALTER TABLE ... DROP COLUMN` on any column
+    // named in a foreign key definition - the column has to be dropped by
+    // rebuilding the table instead: create the new shape, copy the rows
+    // across, then swap it in. `PRAGMA foreign_keys` is a per-connection
+    // setting and has to be off for the swap (sqlite re-validates every FK
+    // referencing `Plan` as soon as it's renamed), so this all runs over one
+    // borrowed connection rather than the pool.
+    let mut conn = db.acquire().await?;
+
+    sqlx::query("PRAGMA foreign_keys = OFF")
+        .execute(&mut *conn)
+        .await?;
+
+    sqlx::query(
+        "CREATE TABLE Plan_new (
+            ID INTEGER PRIMARY KEY,
+            PlanName TEXT NOT NULL,
+            Descr TEXT NOT NULL,
+            StartTime INT NOT NULL,
+            Until INT NOT NULL,
+            Advance INT NOT NULL,
+            Done BOOLEAN NOT NULL,
+            Notify BOOLEAN NOT NULL,
+            Porsmo BOOLEAN NOT NULL
+        )",
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        "INSERT INTO Plan_new (ID, PlanName, Descr, StartTime, Until, Advance, Done, Notify, Porsmo)
+         SELECT ID, PlanName, Descr, StartTime, Until, Advance, Done, Notify, Porsmo FROM Plan",
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query("DROP TABLE Plan").execute(&mut *conn).await?;
+    sqlx::query("ALTER TABLE Plan_new RENAME TO Plan")
+        .execute(&mut *conn)
+        .await?;
+
+    sqlx::query("PRAGMA foreign_keys = ON")
+        .execute(&mut *conn)
+        .await?;
+
+    println!("Migration complete.");
+
+    Ok(())
+}
+
+/// Migrates databases created before [`Plan::tz_name`] existed: adds the
+/// `TzName` column, backfilling existing rows with the system's *current*
+/// IANA zone id. That backfill is an approximation - a row's real original
+/// timezone isn't recoverable, since only the UTC epoch was ever stored -
+/// but it means no pre-existing plan spuriously reports drift until it's
+/// next written by [`insert_plan`]/[`update_plan`]. Idempotent and a no-op
+/// on databases that already have the column (including brand new ones,
+/// which are created with it already in place).
+async fn migrate_add_tz_name(db: &Pool<Sqlite>) -> anyhow::Result<()> {
+    let columns: Vec<(String,)> = sqlx::query_as("SELECT name FROM pragma_table_info('Plan')")
+        .fetch_all(db)
+        .await?;
+
+    if columns.iter().any(|(name,)| name == "TzName") {
+        return Ok(());
+    }
+
+    println!("Adding TzName column to Plan...");
+
+    let tz_name = current_tz_name()?;
+    sqlx::query("ALTER TABLE Plan ADD COLUMN TzName TEXT NOT NULL DEFAULT ''")
+        .execute(db)
+        .await?;
+    sqlx::query("UPDATE Plan SET TzName = ?")
+        .bind(tz_name)
+        .execute(db)
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn encode_decode_color_round_trips_named_ansi_variants() {
+        for color in [
+            Color::Black, Color::Red, Color::Green, Color::Yellow, Color::Blue,
+            Color::Magenta, Color::Cyan, Color::Gray, Color::DarkGray, Color::LightRed,
+            Color::LightGreen, Color::LightYellow, Color::LightBlue, Color::LightMagenta,
+            Color::LightCyan, Color::White,
+        ] {
+            let encoded = encode_color(color).unwrap();
+            assert_eq!(decode_color(encoded), color);
+        }
+    }
+
+    #[test]
+    fn encode_decode_color_round_trips_indexed() {
+        let encoded = encode_color(Color::Indexed(42)).unwrap();
+        assert_eq!(encoded, 42);
+        assert_eq!(decode_color(encoded), Color::Indexed(42));
+    }
+
+    #[test]
+    fn encode_color_rejects_truecolor() {
+        assert!(encode_color(Color::Rgb(1, 2, 3)).is_err());
+    }
+
+    #[test]
+    fn encode_color_rejects_reset() {
+        assert!(encode_color(Color::Reset).is_err());
+    }
+
+    #[test]
+    fn encode_color_rejects_the_no_color_sentinel() {
+        assert!(encode_color(Color::Indexed(NO_COLOR_SENTINEL)).is_err());
+    }
+
+    #[test]
+    fn encode_decode_optional_color_round_trips_none() {
+        let encoded = encode_optional_color(None).unwrap();
+        assert_eq!(encoded, NO_COLOR_SENTINEL);
+        assert_eq!(decode_optional_color(encoded), None);
+    }
+
+    #[test]
+    fn encode_decode_optional_color_round_trips_some() {
+        let encoded = encode_optional_color(Some(Color::White)).unwrap();
+        assert_eq!(decode_optional_color(encoded), Some(Color::White));
+    }
+
+    #[test]
+    fn encode_decode_datetime_round_trips() {
+        let dt = Local.with_ymd_and_hms(2026, 7, 28, 9, 30, 0).unwrap();
+        let encoded = encode_datetime(dt);
+        assert_eq!(decode_datetime(encoded).unwrap(), dt);
+    }
+
+    #[test]
+    fn encode_decode_advance_round_trips_none() {
+        let encoded = encode_advance(None).unwrap();
+        assert_eq!(encoded, NO_ADVANCE_SENTINEL);
+        assert_eq!(decode_advance(encoded).unwrap(), None);
+    }
+
+    #[test]
+    fn encode_decode_advance_round_trips_some() {
+        let delta = TimeDelta::minutes(10);
+        let encoded = encode_advance(Some(delta)).unwrap();
+        assert_eq!(decode_advance(encoded).unwrap(), Some(delta));
+    }
+
+    #[test]
+    fn encode_advance_rejects_the_no_advance_sentinel() {
+        let delta = TimeDelta::try_seconds(NO_ADVANCE_SENTINEL).unwrap();
+        assert!(encode_advance(Some(delta)).is_err());
+    }
+
+    #[test]
+    fn decode_advance_rejects_out_of_range_seconds() {
+        assert!(decode_advance(i64::MAX).is_err());
+    }
+}
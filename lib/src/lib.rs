@@ -0,0 +1,7 @@
+//! `plannrs` library crate. Holds the database, config, daemon, and view
+//! layers consumed by the `app` binary.
+
+pub mod config;
+pub mod daemon;
+pub mod db;
+pub mod view;
@@ -0,0 +1,247 @@
+//! Builds ratatui widgets for plannrs' timeline view. The `Tag` struct was
+//! explicitly designed with `border`/`fill`/`color` for exactly this: each
+//! `Plan` becomes a block spanning its `start`..`until`, styled with its
+//! first `Tag`'s colours (falling back to the grey/black/white scheme
+//! documented on [`Plan::tags`] when it has none).
+//!
+//! [`TimelineBuilder`] only assembles the column/row structure, not a
+//! concrete widget - `add_column`/`add_plan_row` return `&mut Self` so a day
+//! view, a week view (one column per day), and an agenda view can all share
+//! it, differing only in which columns they add and how they bucket plans
+//! into them.
+
+use chrono::{DateTime, Local, Timelike};
+use ratatui::style::{Color, Modifier, Style};
+
+use crate::db::Plan;
+
+/// Fallback fill colour for a `Plan` with no `Tag`s, matching the "default
+/// grey/black/white colour scheme" documented on [`Plan::tags`].
+const DEFAULT_FILL: Color = Color::Gray;
+/// Fallback border colour, paired with [`DEFAULT_FILL`]/[`DEFAULT_FG`].
+const DEFAULT_BORDER: Color = Color::Black;
+/// Fallback foreground (text) colour, paired with [`DEFAULT_FILL`].
+const DEFAULT_FG: Color = Color::White;
+
+/// Glyph prefixed to a plan's label when [`Plan::notify`] is set, marking it
+/// as one that will fire a desktop notification (see [`crate::daemon`]).
+const NOTIFY_GLYPH: &str = "\u{1F514} ";
+
+/// A single plan's position and rendered appearance within the timeline,
+/// computed by [`TimelineBuilder::add_plan_row`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanRow {
+    /// Minutes since midnight on `start`'s day that the block begins at.
+    pub start_min: u32,
+    /// Minutes since midnight on `until`'s day that the block ends at.
+    pub end_min: u32,
+    /// The fill/foreground style the block's interior should be rendered in.
+    pub style: Style,
+    /// The style the block's border should be rendered in, e.g. via
+    /// `Block::default().borders(Borders::ALL).border_style(border_style)`.
+    pub border_style: Style,
+    /// The label text, including [`NOTIFY_GLYPH`] when `notify` is set.
+    pub label: String,
+}
+
+/// Builds up the column/row structure for a timeline-style ratatui widget,
+/// one [`PlanRow`] per `Plan`.
+///
+/// [`columns`](Self::columns) and [`rows`](Self::rows) hand back what's
+/// been assembled so far, for a caller to turn into a concrete widget (e.g.
+/// a `ratatui::widgets::Table`). Keeping the assembly decoupled from any
+/// particular widget is what lets the same builder back a day, week, or
+/// agenda view.
+#[derive(Debug, Default)]
+pub struct TimelineBuilder {
+    columns: Vec<String>,
+    rows: Vec<PlanRow>,
+}
+
+impl TimelineBuilder {
+    /// Creates an empty builder with no columns or rows.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a column header, e.g. a day-of-week label for a week view.
+    pub fn add_column(&mut self, label: impl Into<String>) -> &mut Self {
+        self.columns.push(label.into());
+        self
+    }
+
+    /// Computes a [`PlanRow`] for `plan` and appends it.
+    ///
+    /// The span is minutes since midnight, so plans that don't overlap in
+    /// time never get overlapping spans. Colours come from `plan.tags`'
+    /// first entry, if any; `done` dims and strikes through the style; a
+    /// bell glyph is prefixed to the label when `notify` is set.
+    pub fn add_plan_row(&mut self, plan: &Plan) -> &mut Self {
+        self.rows.push(PlanRow {
+            start_min: minutes_since_midnight(plan.start),
+            end_min: minutes_since_midnight(plan.until),
+            style: plan_style(plan),
+            border_style: plan_border_style(plan),
+            label: plan_label(plan),
+        });
+        self
+    }
+
+    /// The column headers added so far, in insertion order.
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    /// The plan rows added so far, in insertion order.
+    pub fn rows(&self) -> &[PlanRow] {
+        &self.rows
+    }
+}
+
+/// Minutes since midnight on `dt`'s local day, used as the timeline's span
+/// unit - a plan running 09:30-10:15 becomes the span `570..615`.
+fn minutes_since_midnight(dt: DateTime<Local>) -> u32 {
+    dt.hour() * 60 + dt.minute()
+}
+
+/// The fill/foreground style for `plan`'s block: its first tag's `fill`
+/// (background) and `color` (foreground) if it has one, otherwise the
+/// default grey/black/white scheme. `done` plans are dimmed and struck
+/// through rather than recolored, so the tag's colour stays recognisable.
+fn plan_style(plan: &Plan) -> Style {
+    let (bg, fg) = match plan.tags.first() {
+        Some(tag) => (tag.fill.unwrap_or(DEFAULT_FILL), tag.color),
+        None => (DEFAULT_FILL, DEFAULT_FG),
+    };
+
+    let mut style = Style::default().bg(bg).fg(fg);
+    if plan.done {
+        style = style.add_modifier(Modifier::DIM | Modifier::CROSSED_OUT);
+    }
+
+    style
+}
+
+/// The border style for `plan`'s block: its first tag's `border` colour if
+/// set, otherwise the default scheme's border colour.
+fn plan_border_style(plan: &Plan) -> Style {
+    let border = plan
+        .tags
+        .first()
+        .and_then(|tag| tag.border)
+        .unwrap_or(DEFAULT_BORDER);
+
+    Style::default().fg(border)
+}
+
+/// `plan.name`, prefixed with [`NOTIFY_GLYPH`] if `plan.notify` is set.
+fn plan_label(plan: &Plan) -> String {
+    if plan.notify {
+        format!("{NOTIFY_GLYPH}{}", plan.name)
+    } else {
+        plan.name.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Tag;
+    use chrono::TimeZone;
+
+    /// Builds a `Plan` for a non-overlapping slot on the same day, with no
+    /// tags and the given `done`/`notify` flags.
+    fn plan_at(start_hm: (u32, u32), until_hm: (u32, u32), done: bool, notify: bool) -> Plan {
+        Plan {
+            id: 0,
+            name: "Study".to_string(),
+            description: String::new(),
+            start: Local.with_ymd_and_hms(2026, 7, 28, start_hm.0, start_hm.1, 0).unwrap(),
+            until: Local.with_ymd_and_hms(2026, 7, 28, until_hm.0, until_hm.1, 0).unwrap(),
+            advance: None,
+            done,
+            tags: Vec::new(),
+            notify,
+            porsmo: false,
+            tz_name: String::new(),
+        }
+    }
+
+    #[test]
+    fn computes_non_overlapping_spans_in_minutes() {
+        let morning = plan_at((9, 0), (10, 30), false, false);
+        let afternoon = plan_at((13, 15), (14, 0), false, false);
+
+        let mut builder = TimelineBuilder::new();
+        builder.add_plan_row(&morning).add_plan_row(&afternoon);
+
+        let rows = builder.rows();
+        assert_eq!(rows[0].start_min, 9 * 60);
+        assert_eq!(rows[0].end_min, 10 * 60 + 30);
+        assert_eq!(rows[1].start_min, 13 * 60 + 15);
+        assert_eq!(rows[1].end_min, 14 * 60);
+        assert!(rows[0].end_min <= rows[1].start_min, "spans must not overlap");
+    }
+
+    #[test]
+    fn falls_back_to_default_colour_scheme_without_tags() {
+        let plan = plan_at((9, 0), (10, 0), false, false);
+
+        let mut builder = TimelineBuilder::new();
+        builder.add_plan_row(&plan);
+
+        let row = &builder.rows()[0];
+        assert_eq!(row.style, Style::default().bg(DEFAULT_FILL).fg(DEFAULT_FG));
+        assert_eq!(row.border_style, Style::default().fg(DEFAULT_BORDER));
+    }
+
+    #[test]
+    fn uses_first_tags_colours_when_present() {
+        let mut plan = plan_at((9, 0), (10, 0), false, false);
+        plan.tags.push(Tag {
+            id: 1,
+            name: "Maths".to_string(),
+            border: Some(Color::Red),
+            fill: Some(Color::Blue),
+            color: Color::White,
+        });
+
+        let mut builder = TimelineBuilder::new();
+        builder.add_plan_row(&plan);
+
+        let row = &builder.rows()[0];
+        assert_eq!(row.style, Style::default().bg(Color::Blue).fg(Color::White));
+        assert_eq!(row.border_style, Style::default().fg(Color::Red));
+    }
+
+    #[test]
+    fn done_plans_are_dimmed_and_struck_through() {
+        let plan = plan_at((9, 0), (10, 0), true, false);
+
+        let mut builder = TimelineBuilder::new();
+        builder.add_plan_row(&plan);
+
+        let style = builder.rows()[0].style;
+        assert!(style.add_modifier.contains(Modifier::DIM));
+        assert!(style.add_modifier.contains(Modifier::CROSSED_OUT));
+    }
+
+    #[test]
+    fn notify_plans_get_a_bell_glyph() {
+        let plan = plan_at((9, 0), (10, 0), false, true);
+
+        let mut builder = TimelineBuilder::new();
+        builder.add_plan_row(&plan);
+
+        assert!(builder.rows()[0].label.starts_with(NOTIFY_GLYPH));
+        assert!(builder.rows()[0].label.ends_with("Study"));
+    }
+
+    #[test]
+    fn columns_are_kept_in_insertion_order() {
+        let mut builder = TimelineBuilder::new();
+        builder.add_column("Mon").add_column("Tue");
+
+        assert_eq!(builder.columns(), &["Mon".to_string(), "Tue".to_string()]);
+    }
+}